@@ -1,9 +1,8 @@
-use serde::Serialize;
-use serde_json;
+use serde::{Deserialize, Serialize};
 use valico::json_schema;
 use wasm_bindgen::prelude::*;
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct ValidationResult {
     valid: bool,
     line: Option<u32>,
@@ -13,90 +12,282 @@ struct ValidationResult {
     message: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct SchemaError {
     path: String,
     message: String,
+    line: Option<u32>,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct SchemaValidationResult {
     valid: bool,
     errors: Vec<SchemaError>,
 }
 
-#[wasm_bindgen]
-pub fn validate_toml(content: &str) -> String {
-    let result = toml::from_str::<toml::Value>(content);
-    let index = LineIndex::new(content);
+#[derive(Serialize, Deserialize)]
+struct ConversionResult {
+    ok: bool,
+    json: Option<String>,
+    error: Option<ValidationResult>,
+}
 
-    let validation = match result {
-        Ok(_) => ValidationResult {
-            valid: true,
+/// Parses `content` as TOML, YAML, or JSON and returns canonical JSON.
+///
+/// `format` selects the source syntax (`"toml"`, `"yaml"`, or `"json"`,
+/// case-insensitive). When `None`, each parser is tried in turn and the
+/// first one that succeeds wins, with TOML attempted first since it is
+/// this crate's primary format.
+#[wasm_bindgen]
+pub fn convert_to_json(content: &str, format: Option<String>) -> String {
+    let result = match format.as_deref().map(|f| f.to_ascii_lowercase()) {
+        Some(f) if f == "toml" => parse_toml_to_json(content),
+        Some(f) if f == "yaml" || f == "yml" => parse_yaml_to_json(content),
+        Some(f) if f == "json" => parse_json_to_json(content),
+        Some(other) => Err(ValidationResult {
+            valid: false,
             line: None,
             column: None,
             end_line: None,
             end_column: None,
-            message: None,
+            message: Some(format!("Unknown format: {other}")),
+        }),
+        None => parse_toml_to_json(content)
+            .or_else(|_| parse_yaml_to_json(content))
+            .or_else(|_| parse_json_to_json(content)),
+    };
+
+    let conversion = match result {
+        Ok(json) => ConversionResult {
+            ok: true,
+            json: Some(json),
+            error: None,
         },
-        Err(error) => {
-            let (start_line, start_col, end_line, end_col) = if let Some(range) = error.span() {
-                let mut start_offset = range.start;
-                let mut end_offset = range.end;
-
-                while start_offset > 0 {
-                    let prev = content[..start_offset]
-                        .char_indices()
-                        .last()
-                        .map(|(i, _)| i)
-                        .unwrap_or(0);
-                    let c = content[prev..].chars().next().unwrap();
-                    if c.is_whitespace()
-                        || c == '='
-                        || c == '['
-                        || c == '{'
-                        || c == ','
-                        || c == '"'
-                        || c == '\''
-                    {
-                        break;
-                    }
-                    start_offset = prev;
-                }
-                while end_offset < content.len() {
-                    let c = content[end_offset..].chars().next().unwrap();
-                    if c.is_whitespace()
-                        || c == '#'
-                        || c == ']'
-                        || c == '}'
-                        || c == ','
-                        || c == '"'
-                        || c == '\''
-                    {
-                        break;
-                    }
-                    end_offset += c.len_utf8();
-                }
+        Err(error) => ConversionResult {
+            ok: false,
+            json: None,
+            error: Some(error),
+        },
+    };
 
-                let start = index.coords(start_offset, content);
-                let end = index.coords(end_offset, content);
-                (Some(start.0), Some(start.1), Some(end.0), Some(end.1))
-            } else {
-                (None, None, None, None)
-            };
+    serde_json::to_string(&conversion).unwrap()
+}
 
-            ValidationResult {
-                valid: false,
-                line: start_line,
-                column: start_col,
-                end_line,
-                end_column: end_col,
-                message: Some(error.to_string()),
-            }
+fn parse_toml_to_json(content: &str) -> Result<String, ValidationResult> {
+    let index = LineIndex::new(content);
+    // Parsed with `toml::Value` first purely for its precise span-reporting
+    // error type; the actual JSON conversion below goes through `toml_edit`
+    // instead, since `toml::Value`'s `Serialize` impl leaks a private
+    // wrapper object for datetimes rather than a plain JSON string.
+    toml::from_str::<toml::Value>(content).map_err(|error| {
+        toml_error_to_validation_result(&error, content, &index)
+    })?;
+    let document = content.parse::<toml_edit::DocumentMut>().map_err(|error| ValidationResult {
+        valid: false,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: Some(error.to_string()),
+    })?;
+    let json_value = toml_document_to_json(&document);
+    Ok(serde_json::to_string(&json_value).unwrap())
+}
+
+fn parse_yaml_to_json(content: &str) -> Result<String, ValidationResult> {
+    let value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|error| {
+        let (line, column) = error
+            .location()
+            .map(|loc| (Some(loc.line() as u32), Some(loc.column() as u32)))
+            .unwrap_or((None, None));
+        ValidationResult {
+            valid: false,
+            line,
+            column,
+            end_line: line,
+            end_column: column,
+            message: Some(error.to_string()),
+        }
+    })?;
+    let json_value: serde_json::Value = serde_json::to_value(value).map_err(|error| ValidationResult {
+        valid: false,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: Some(error.to_string()),
+    })?;
+    Ok(serde_json::to_string(&json_value).unwrap())
+}
+
+fn parse_json_to_json(content: &str) -> Result<String, ValidationResult> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|error| ValidationResult {
+        valid: false,
+        line: Some(error.line() as u32),
+        column: Some(error.column() as u32),
+        end_line: Some(error.line() as u32),
+        end_column: Some(error.column() as u32),
+        message: Some(error.to_string()),
+    })?;
+    Ok(serde_json::to_string(&value).unwrap())
+}
+
+fn toml_error_to_validation_result(
+    error: &toml::de::Error,
+    content: &str,
+    index: &LineIndex,
+) -> ValidationResult {
+    let (start_line, start_col, end_line, end_col) = if let Some(range) = error.span() {
+        let start = index.coords(range.start, content);
+        let end = index.coords(range.end, content);
+        (Some(start.0), Some(start.1), Some(end.0), Some(end.1))
+    } else {
+        (None, None, None, None)
+    };
+
+    ValidationResult {
+        valid: false,
+        line: start_line,
+        column: start_col,
+        end_line,
+        end_column: end_col,
+        message: Some(error.to_string()),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TomlValidationResult {
+    valid: bool,
+    diagnostics: Vec<ValidationResult>,
+}
+
+/// Caps how many diagnostics a single recovery pass reports, so a
+/// pathological structural error (e.g. an unterminated string at the top
+/// of the file) can't cascade into hundreds of spurious follow-on errors.
+const MAX_DIAGNOSTICS: usize = 50;
+
+#[wasm_bindgen]
+pub fn validate_toml(content: &str) -> String {
+    let diagnostics = collect_toml_diagnostics(content);
+    let result = TomlValidationResult {
+        valid: diagnostics.is_empty(),
+        diagnostics,
+    };
+    serde_json::to_string(&result).unwrap()
+}
+
+/// Repeatedly parses `content`, and on each failure blanks out the
+/// offending line (preserving line numbers for everything after it) and
+/// retries, so one bad line doesn't hide syntax errors elsewhere in the
+/// file. A diagnostic that lands on a line already reported is not
+/// reported again and ends the recovery (see below), since blanking can
+/// occasionally make a line re-trigger a different message on the same
+/// span.
+///
+/// Bounded two ways, independent of how many diagnostics get reported:
+/// blanking a line that's already blank (because we already blanked it on
+/// an earlier pass, or it keeps re-anchoring the same "unterminated"
+/// error at EOF) is a no-op, so that case breaks immediately instead of
+/// looping forever with `diagnostics` frozen; `max_iterations` is a
+/// second, coarser backstop against any recovery pattern that isn't a
+/// simple repeat.
+fn collect_toml_diagnostics(content: &str) -> Vec<ValidationResult> {
+    let mut lines: Vec<&str> = content.split('\n').collect();
+    let mut seen_lines = std::collections::HashSet::new();
+    let mut diagnostics = Vec::new();
+    let max_iterations = lines.len().saturating_add(1);
+
+    for _ in 0..max_iterations {
+        if diagnostics.len() >= MAX_DIAGNOSTICS {
+            break;
+        }
+
+        let working = lines.join("\n");
+        let error = match toml::from_str::<toml::Value>(&working) {
+            Ok(_) => break,
+            Err(error) => error,
+        };
+
+        // Re-indexed against `working` on each pass: blanking earlier
+        // lines shifts every later byte offset, even though line numbers
+        // themselves stay stable.
+        let index = LineIndex::new(&working);
+        let diagnostic = toml_error_to_diagnostic(&error, &working, &index);
+        let Some(line) = diagnostic.line else {
+            diagnostics.push(diagnostic);
+            break;
+        };
+
+        if !seen_lines.insert(line) {
+            // Already reported (and blanked) this line on an earlier
+            // pass, yet the parser is still anchoring its error here --
+            // blanking it again is a no-op, so looping further can't
+            // make progress. Stop instead of spinning forever.
+            break;
         }
+
+        let Some(slot) = lines.get_mut(line as usize) else {
+            break;
+        };
+
+        diagnostics.push(diagnostic);
+        *slot = "";
+    }
+
+    diagnostics
+}
+
+fn toml_error_to_diagnostic(error: &toml::de::Error, content: &str, index: &LineIndex) -> ValidationResult {
+    let (start_line, start_col, end_line, end_col) = if let Some(range) = error.span() {
+        let (start_offset, end_offset) = widen_span(content, range);
+        let start = index.coords(start_offset, content);
+        let end = index.coords(end_offset, content);
+        (Some(start.0), Some(start.1), Some(end.0), Some(end.1))
+    } else {
+        (None, None, None, None)
     };
 
-    serde_json::to_string(&validation).unwrap()
+    ValidationResult {
+        valid: false,
+        line: start_line,
+        column: start_col,
+        end_line,
+        end_column: end_col,
+        message: Some(error.to_string()),
+    }
+}
+
+/// Widens a `toml::de::Error` span out to the nearest token boundary, so
+/// a single misplaced character doesn't report a zero-width or
+/// sub-token span in the UI.
+fn widen_span(content: &str, range: std::ops::Range<usize>) -> (usize, usize) {
+    let mut start_offset = range.start;
+    let mut end_offset = range.end;
+
+    while start_offset > 0 {
+        let prev = content[..start_offset]
+            .char_indices()
+            .last()
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let c = content[prev..].chars().next().unwrap();
+        if c.is_whitespace() || c == '=' || c == '[' || c == '{' || c == ',' || c == '"' || c == '\'' {
+            break;
+        }
+        start_offset = prev;
+    }
+    while end_offset < content.len() {
+        let c = content[end_offset..].chars().next().unwrap();
+        if c.is_whitespace() || c == '#' || c == ']' || c == '}' || c == ',' || c == '"' || c == '\'' {
+            break;
+        }
+        end_offset += c.len_utf8();
+    }
+
+    (start_offset, end_offset)
 }
 
 struct LineIndex {
@@ -126,78 +317,1359 @@ impl LineIndex {
 }
 
 #[wasm_bindgen]
-pub fn validate_with_schema(toml_content: &str, json_schema: &str) -> String {
-    let toml_value = match toml::from_str::<toml::Value>(toml_content) {
-        Ok(v) => v,
+pub fn validate_with_schema(toml_content: &str, json_schema: &str, config: Option<String>) -> String {
+    match compile_schema(json_schema, config.as_deref()) {
+        Ok((scope, schema_id, schema_json, formats, ref_schemas)) => {
+            validate_impl(&scope, &schema_id, &schema_json, &formats, &ref_schemas, toml_content)
+        }
+        Err(result) => serde_json::to_string(&result).unwrap(),
+    }
+}
+
+/// Caller-supplied options for compiling a JSON Schema: which draft's
+/// keyword set to validate against, and any remote `$ref` targets the
+/// schema depends on but doesn't define inline.
+///
+/// `refs` maps a `$ref` URI (as it appears in the schema, e.g.
+/// `"https://example.com/address.json"`) to the JSON Schema document it
+/// points at. Each is registered with the scope before the main schema is
+/// compiled, so cross-file references resolve instead of failing.
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct SchemaConfig {
+    draft: Option<String>,
+    refs: Option<std::collections::HashMap<String, serde_json::Value>>,
+    /// Extra `format` keyword checkers, as `name -> regex pattern`. These
+    /// are registered alongside the TOML-native built-ins (`date-time`,
+    /// `date`, `time`, `duration`) rather than replacing them.
+    formats: Option<std::collections::HashMap<String, String>>,
+}
+
+impl SchemaConfig {
+    fn parse(config: Option<&str>) -> Result<Self, SchemaValidationResult> {
+        match config {
+            None => Ok(SchemaConfig::default()),
+            Some(raw) => serde_json::from_str(raw).map_err(|e| SchemaValidationResult {
+                valid: false,
+                errors: vec![SchemaError {
+                    path: String::from("config"),
+                    message: format!("Invalid schema config: {e}"),
+                    line: None,
+                    column: None,
+                    end_line: None,
+                    end_column: None,
+                }],
+            }),
+        }
+    }
+
+    fn draft(&self) -> json_schema::SchemaVersion {
+        match self.draft.as_deref() {
+            Some("2019-09") | Some("draft2019-09") => json_schema::SchemaVersion::Draft2019_09,
+            _ => json_schema::SchemaVersion::Draft7,
+        }
+    }
+}
+
+/// A named `format` keyword checker: either one of the TOML-native
+/// built-ins (matched against the string `toml_document_to_json` renders
+/// for a datetime, which preserves enough of the original syntax to tell
+/// an offset datetime from a local one) or a caller-supplied regex.
+enum FormatChecker {
+    BuiltIn(fn(&str) -> bool),
+    Regex(regex::Regex),
+}
+
+impl FormatChecker {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            FormatChecker::BuiltIn(check) => check(value),
+            FormatChecker::Regex(re) => re.is_match(value),
+        }
+    }
+}
+
+/// Checkers for the `format` keyword, keyed by format name. Callers
+/// extend this via `SchemaConfig::formats`; it never replaces the
+/// TOML-native built-ins, only adds to them.
+struct FormatRegistry {
+    checkers: std::collections::HashMap<String, FormatChecker>,
+}
+
+impl FormatRegistry {
+    fn new(custom: &std::collections::HashMap<String, String>) -> Result<Self, SchemaValidationResult> {
+        let mut checkers = std::collections::HashMap::new();
+        checkers.insert("date-time".to_string(), FormatChecker::BuiltIn(is_offset_date_time));
+        checkers.insert("date".to_string(), FormatChecker::BuiltIn(is_local_date));
+        checkers.insert("time".to_string(), FormatChecker::BuiltIn(is_local_time));
+        checkers.insert("duration".to_string(), FormatChecker::BuiltIn(is_duration));
+
+        for (name, pattern) in custom {
+            if checkers.contains_key(name) {
+                return Err(SchemaValidationResult {
+                    valid: false,
+                    errors: vec![SchemaError {
+                        path: format!("format:{name}"),
+                        message: format!("\"{name}\" is a built-in format and cannot be overridden"),
+                        line: None,
+                        column: None,
+                        end_line: None,
+                        end_column: None,
+                    }],
+                });
+            }
+
+            let re = regex::Regex::new(&format!("^(?:{pattern})$")).map_err(|e| SchemaValidationResult {
+                valid: false,
+                errors: vec![SchemaError {
+                    path: format!("format:{name}"),
+                    message: format!("Invalid format pattern: {e}"),
+                    line: None,
+                    column: None,
+                    end_line: None,
+                    end_column: None,
+                }],
+            })?;
+            checkers.insert(name.clone(), FormatChecker::Regex(re));
+        }
+
+        Ok(FormatRegistry { checkers })
+    }
+
+    fn check(&self, name: &str, value: &str) -> bool {
+        match self.checkers.get(name) {
+            Some(checker) => checker.matches(value),
+            // Unknown format names are annotations, not validated keywords.
+            None => true,
+        }
+    }
+}
+
+fn is_offset_date_time(s: &str) -> bool {
+    let Some((date, time)) = s.split_once('T').or_else(|| s.split_once(' ')) else {
+        return false;
+    };
+    is_local_date(date)
+        && (time.ends_with('Z')
+            || time.ends_with('z')
+            || time.rfind(['+', '-']).is_some_and(|i| i > 0))
+        && is_local_time(time.trim_end_matches(['Z', 'z']).split(['+', '-']).next().unwrap_or(""))
+}
+
+fn is_local_date(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && s[0..4].bytes().all(|b| b.is_ascii_digit())
+        && s[5..7].bytes().all(|b| b.is_ascii_digit())
+        && s[8..10].bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_local_time(s: &str) -> bool {
+    let head = s.split(['.', 'Z', 'z']).next().unwrap_or(s);
+    let bytes = head.as_bytes();
+    bytes.len() == 8
+        && bytes[2] == b':'
+        && bytes[5] == b':'
+        && head[0..2].bytes().all(|b| b.is_ascii_digit())
+        && head[3..5].bytes().all(|b| b.is_ascii_digit())
+        && head[6..8].bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Loosely validates an ISO 8601 duration (`P1DT2H`, `PT30M`, ...); TOML
+/// has no native duration type, so schemas express one as a plain string.
+fn is_duration(s: &str) -> bool {
+    let Some(rest) = s.strip_prefix('P') else {
+        return false;
+    };
+    if rest.is_empty() {
+        return false;
+    }
+    let (date_part, time_part) = rest.split_once('T').unwrap_or((rest, ""));
+    if rest.contains('T') && time_part.is_empty() {
+        return false;
+    }
+    let has_date_component = duration_component(date_part, "YMWD");
+    let has_time_component = time_part.is_empty() || duration_component(time_part, "HMS");
+    has_date_component && has_time_component
+}
+
+fn duration_component(s: &str, units: &str) -> bool {
+    if s.is_empty() {
+        return true;
+    }
+    let mut digits_seen = false;
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits_seen = true;
+            continue;
+        }
+        if !digits_seen || !units.contains(c) {
+            return false;
+        }
+        digits_seen = false;
+    }
+    !digits_seen
+}
+
+/// A JSON Schema compiled once and reused across many `validate` calls,
+/// avoiding the recompilation cost `validate_with_schema` pays on every
+/// invocation. Construct with the schema source, then call `validate`
+/// for each TOML document checked against it.
+///
+/// valico's `Scope::compile_and_return` hands back a schema view that
+/// borrows the `Scope` it came from, so it can't be stored alongside an
+/// owned `Scope` in the same struct. Instead we keep the `Scope` plus the
+/// `Url` the schema was registered under (`Scope::compile`) and resolve
+/// it fresh on each `validate` call via `Scope::resolve`.
+#[wasm_bindgen]
+pub struct SchemaValidator {
+    scope: json_schema::Scope,
+    schema_id: url::Url,
+    schema_json: serde_json::Value,
+    formats: FormatRegistry,
+    ref_schemas: std::collections::HashMap<String, serde_json::Value>,
+}
+
+#[wasm_bindgen]
+impl SchemaValidator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(json_schema: &str, config: Option<String>) -> Result<SchemaValidator, JsValue> {
+        match compile_schema(json_schema, config.as_deref()) {
+            Ok((scope, schema_id, schema_json, formats, ref_schemas)) => Ok(SchemaValidator {
+                scope,
+                schema_id,
+                schema_json,
+                formats,
+                ref_schemas,
+            }),
+            Err(result) => Err(JsValue::from_str(&serde_json::to_string(&result).unwrap())),
+        }
+    }
+
+    pub fn validate(&self, toml_content: &str) -> String {
+        validate_impl(
+            &self.scope,
+            &self.schema_id,
+            &self.schema_json,
+            &self.formats,
+            &self.ref_schemas,
+            toml_content,
+        )
+    }
+}
+
+/// Parses `toml_content`, runs it through the already-compiled schema
+/// identified by `schema_id` within `scope`, and serializes the result.
+/// Shared by the stateless `validate_with_schema` wrapper and
+/// `SchemaValidator::validate` so both paths agree on TOML-parse-error
+/// and span-resolution behavior.
+fn validate_impl(
+    scope: &json_schema::Scope,
+    schema_id: &url::Url,
+    schema_json: &serde_json::Value,
+    formats: &FormatRegistry,
+    ref_schemas: &std::collections::HashMap<String, serde_json::Value>,
+    toml_content: &str,
+) -> String {
+    let document = match toml_content.parse::<toml_edit::DocumentMut>() {
+        Ok(d) => d,
         Err(_) => {
             return serde_json::to_string(&SchemaValidationResult {
                 valid: false,
                 errors: vec![SchemaError {
                     path: String::from("root"),
                     message: String::from("Invalid TOML syntax"),
+                    line: None,
+                    column: None,
+                    end_line: None,
+                    end_column: None,
                 }],
             })
             .unwrap();
         }
     };
 
-    let json_value = serde_json::to_value(toml_value).unwrap();
-    let mut schema_json: serde_json::Value = serde_json::from_str(json_schema).unwrap();
+    let json_value = toml_document_to_json(&document);
+
+    let result = run_validation(
+        scope,
+        schema_id,
+        schema_json,
+        formats,
+        ref_schemas,
+        &json_value,
+        &document,
+        toml_content,
+    );
+    serde_json::to_string(&result).unwrap()
+}
+
+/// JSON Schema keywords valico cannot compile. Unlike the old blanket
+/// `x-` stripping, this only removes keywords that would otherwise abort
+/// the whole compile, leaving genuine vendor/extension keys (including
+/// `x-*`) intact for consumers that care about them.
+const UNSUPPORTED_KEYWORDS: &[&str] = &["$comment", "unevaluatedProperties", "unevaluatedItems"];
+
+/// Everything `compile_schema` hands back to its two callers
+/// (`validate_with_schema` and `SchemaValidator::new`): the compiled
+/// schema itself, the root schema JSON (for `check_formats`'s raw walk),
+/// the format checkers, and the raw `$ref` targets (for resolving a
+/// `$ref` during that same walk).
+type CompiledSchema = (
+    json_schema::Scope,
+    url::Url,
+    serde_json::Value,
+    FormatRegistry,
+    std::collections::HashMap<String, serde_json::Value>,
+);
+
+/// Parses and compiles `json_schema` under the draft and `$ref` targets
+/// named by `config` (or draft7 with no extra refs, if `config` is `None`).
+fn compile_schema(json_schema: &str, config: Option<&str>) -> Result<CompiledSchema, SchemaValidationResult> {
+    let config = SchemaConfig::parse(config)?;
+    let formats = FormatRegistry::new(config.formats.as_ref().unwrap_or(&std::collections::HashMap::new()))?;
 
-    // Sanitize the schema before compiling
-    sanitize_json(&mut schema_json);
+    let mut schema_json: serde_json::Value = serde_json::from_str(json_schema).map_err(|e| {
+        SchemaValidationResult {
+            valid: false,
+            errors: vec![SchemaError {
+                path: String::from("schema"),
+                message: format!("Invalid JSON Schema: {e}"),
+                line: None,
+                column: None,
+                end_line: None,
+                end_column: None,
+            }],
+        }
+    })?;
+
+    // Strip only the keywords valico can't compile; `$defs`/`definitions`
+    // and `x-` extensions are left in place so `$ref` resolution and
+    // downstream consumers keep working.
+    strip_unsupported_keywords(&mut schema_json);
+
+    let mut scope = json_schema::Scope::without_formats(config.draft());
+
+    // Kept alongside the compiled `Scope` so `check_formats` can resolve a
+    // `$ref` it encounters while walking the schema: valico resolves refs
+    // internally during `validate()`, but that's a different traversal
+    // from the raw-JSON walk `check_formats` does to find `format` keys.
+    let mut ref_schemas = std::collections::HashMap::new();
+    let mut ref_errors = Vec::new();
+    if let Some(refs) = &config.refs {
+        for (uri, mut ref_schema) in refs.clone() {
+            if let serde_json::Value::Object(obj) = &mut ref_schema {
+                obj.entry("$id").or_insert_with(|| serde_json::Value::String(uri.clone()));
+            }
+            strip_unsupported_keywords(&mut ref_schema);
+            ref_schemas.insert(uri.clone(), ref_schema.clone());
+            if let Err(e) = scope.compile(ref_schema, false) {
+                ref_errors.push(SchemaError {
+                    path: format!("$ref:{uri}"),
+                    message: format!("Could not resolve remote $ref: {:?}", e),
+                    line: None,
+                    column: None,
+                    end_line: None,
+                    end_column: None,
+                });
+            }
+        }
+    }
+
+    if !ref_errors.is_empty() {
+        return Err(SchemaValidationResult {
+            valid: false,
+            errors: ref_errors,
+        });
+    }
+
+    let schema_json_for_formats = schema_json.clone();
+    match scope.compile(schema_json, false) {
+        Ok(schema_id) => Ok((scope, schema_id, schema_json_for_formats, formats, ref_schemas)),
+        Err(e) => Err(SchemaValidationResult {
+            valid: false,
+            errors: vec![SchemaError {
+                path: String::from("schema"),
+                message: format!("Invalid JSON Schema: {:?}", e),
+                line: None,
+                column: None,
+                end_line: None,
+                end_column: None,
+            }],
+        }),
+    }
+}
+
+/// Resolves `$ref` strings (as they appear in the raw schema JSON, before
+/// valico compiles it) to the schema they point at, so code that walks the
+/// raw schema -- like [`check_formats`] -- can follow a `$ref` the same
+/// way valico does internally during `validate()`.
+///
+/// Handles the two forms `compile_schema` supports: a local JSON Pointer
+/// fragment (`"#/$defs/Foo"`, resolved against the root schema) and a
+/// remote URI (resolved against `SchemaConfig::refs`, keyed exactly as the
+/// caller supplied it).
+struct RefResolver<'a> {
+    root: &'a serde_json::Value,
+    remote: &'a std::collections::HashMap<String, serde_json::Value>,
+}
+
+impl<'a> RefResolver<'a> {
+    fn resolve(&self, ref_str: &str) -> Option<&'a serde_json::Value> {
+        match ref_str.strip_prefix('#') {
+            Some("") => Some(self.root),
+            Some(fragment) => self.root.pointer(fragment),
+            None => self.remote.get(ref_str),
+        }
+    }
+}
+
+/// Resolves `schema_id` in `scope` and validates `json_value` against it,
+/// mapping any failures back to TOML spans in `document`/`toml_content`
+/// via [`build_span_table`], then runs the `format` keyword checks the
+/// schema itself doesn't reliably enforce.
+#[allow(clippy::too_many_arguments)]
+fn run_validation(
+    scope: &json_schema::Scope,
+    schema_id: &url::Url,
+    schema_json: &serde_json::Value,
+    formats: &FormatRegistry,
+    ref_schemas: &std::collections::HashMap<String, serde_json::Value>,
+    json_value: &serde_json::Value,
+    document: &toml_edit::DocumentMut,
+    toml_content: &str,
+) -> SchemaValidationResult {
+    let schema = scope
+        .resolve(schema_id)
+        .expect("schema_id was registered by compile_schema's scope.compile call");
+    let validation = schema.validate(json_value);
+
+    let index = LineIndex::new(toml_content);
+    let span_table = build_span_table(document);
+    let mut errors_vec = Vec::new();
+    for error in validation.errors {
+        let pointer = error.get_path().to_string();
+        let span = resolve_pointer_span(&span_table, &pointer);
+        let (line, column, end_line, end_column) = match span {
+            Some(range) => {
+                let start = index.coords(range.start, toml_content);
+                let end = index.coords(range.end, toml_content);
+                (Some(start.0), Some(start.1), Some(end.0), Some(end.1))
+            }
+            None => (None, None, None, None),
+        };
+        errors_vec.push(SchemaError {
+            path: pointer,
+            message: error.get_title().to_string(),
+            line,
+            column,
+            end_line,
+            end_column,
+        });
+    }
+
+    // `validation.errors` only covers constraints valico could actually
+    // check; a `$ref` it couldn't resolve (e.g. a typo'd remote URI or an
+    // `$anchor` the active draft doesn't understand) is reported separately
+    // in `missing` and otherwise validates silently. Surface each as its
+    // own error so an unresolved `$ref` doesn't pass as if unconstrained.
+    for unresolved in validation.missing {
+        errors_vec.push(SchemaError {
+            path: format!("$ref:{unresolved}"),
+            message: format!("Could not resolve $ref: {unresolved}"),
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+        });
+    }
+
+    let refs = RefResolver {
+        root: schema_json,
+        remote: ref_schemas,
+    };
+    check_formats(schema_json, json_value, String::new(), formats, &refs, &span_table, &index, toml_content, &mut errors_vec);
+
+    SchemaValidationResult {
+        valid: errors_vec.is_empty(),
+        errors: errors_vec,
+    }
+}
+
+/// How many `$ref` hops [`check_formats`] will follow before giving up on
+/// a single schema node, as a backstop against a `$ref` cycle.
+const MAX_REF_DEPTH: u8 = 16;
+
+/// Walks `schema`/`instance` in lockstep along `properties`/`items`,
+/// checking any `format` keyword against `formats` and recording a
+/// [`SchemaError`] (with a resolved TOML span) for each mismatch. Follows
+/// `$ref` (via `refs`) before inspecting a schema node, so a property
+/// whose schema is expressed through a `$ref` still gets its `format`
+/// checked instead of being silently skipped.
+#[allow(clippy::too_many_arguments)]
+fn check_formats(
+    schema: &serde_json::Value,
+    instance: &serde_json::Value,
+    pointer: String,
+    formats: &FormatRegistry,
+    refs: &RefResolver<'_>,
+    span_table: &std::collections::HashMap<String, std::ops::Range<usize>>,
+    index: &LineIndex,
+    toml_content: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let mut schema = schema;
+    for _ in 0..MAX_REF_DEPTH {
+        let Some(ref_str) = schema.as_object().and_then(|o| o.get("$ref")).and_then(|v| v.as_str()) else {
+            break;
+        };
+        let Some(target) = refs.resolve(ref_str) else {
+            return;
+        };
+        schema = target;
+    }
+
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let (Some(format), Some(value)) = (
+        schema.get("format").and_then(|v| v.as_str()),
+        instance.as_str(),
+    ) {
+        if !formats.check(format, value) {
+            let span = resolve_pointer_span(span_table, &pointer);
+            let (line, column, end_line, end_column) = match span {
+                Some(range) => {
+                    let start = index.coords(range.start, toml_content);
+                    let end = index.coords(range.end, toml_content);
+                    (Some(start.0), Some(start.1), Some(end.0), Some(end.1))
+                }
+                None => (None, None, None, None),
+            };
+            errors.push(SchemaError {
+                path: if pointer.is_empty() { "/".to_string() } else { pointer.clone() },
+                message: format!("\"{value}\" does not match format \"{format}\""),
+                line,
+                column,
+                end_line,
+                end_column,
+            });
+        }
+    }
+
+    if let (Some(props), Some(obj)) = (
+        schema.get("properties").and_then(|v| v.as_object()),
+        instance.as_object(),
+    ) {
+        for (key, sub_schema) in props {
+            if let Some(value) = obj.get(key) {
+                check_formats(
+                    sub_schema,
+                    value,
+                    format!("{pointer}/{key}"),
+                    formats,
+                    refs,
+                    span_table,
+                    index,
+                    toml_content,
+                    errors,
+                );
+            }
+        }
+    }
+
+    if let (Some(items_schema), Some(arr)) = (schema.get("items"), instance.as_array()) {
+        for (i, value) in arr.iter().enumerate() {
+            check_formats(
+                items_schema,
+                value,
+                format!("{pointer}/{i}"),
+                formats,
+                refs,
+                span_table,
+                index,
+                toml_content,
+                errors,
+            );
+        }
+    }
+}
+
+/// Converts a parsed `toml_edit` document to `serde_json::Value`. Walks the
+/// `toml_edit` tree directly rather than going through `toml::Value`, whose
+/// serde `Serialize` impl represents a datetime as a private wrapper object
+/// (`{"$__toml_private_datetime": "..."}`) instead of a plain JSON string.
+fn toml_document_to_json(document: &toml_edit::DocumentMut) -> serde_json::Value {
+    toml_table_to_json(document.as_table())
+}
 
-    let mut scope = json_schema::Scope::new();
-    let schema = match scope.compile_and_return(schema_json, false) {
-        Ok(s) => s,
+fn toml_table_to_json(table: &toml_edit::Table) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    for (key, item) in table.iter() {
+        map.insert(key.to_string(), toml_item_to_json(item));
+    }
+    serde_json::Value::Object(map)
+}
+
+fn toml_item_to_json(item: &toml_edit::Item) -> serde_json::Value {
+    match item {
+        toml_edit::Item::None => serde_json::Value::Null,
+        toml_edit::Item::Value(value) => toml_value_to_json(value),
+        toml_edit::Item::Table(table) => toml_table_to_json(table),
+        toml_edit::Item::ArrayOfTables(array) => {
+            serde_json::Value::Array(array.iter().map(toml_table_to_json).collect())
+        }
+    }
+}
+
+fn toml_value_to_json(value: &toml_edit::Value) -> serde_json::Value {
+    match value {
+        toml_edit::Value::String(s) => serde_json::Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => serde_json::Value::Number((*i.value()).into()),
+        toml_edit::Value::Float(f) => serde_json::Number::from_f64(*f.value())
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        toml_edit::Value::Boolean(b) => serde_json::Value::Bool(*b.value()),
+        // TOML's three datetime flavors (offset, local date-time, local
+        // date/time) have no single JSON equivalent; render the same
+        // string form `toml_edit` would serialize back into the document.
+        toml_edit::Value::Datetime(d) => serde_json::Value::String(d.value().to_string()),
+        toml_edit::Value::Array(arr) => {
+            serde_json::Value::Array(arr.iter().map(toml_value_to_json).collect())
+        }
+        toml_edit::Value::InlineTable(inline) => {
+            let mut map = serde_json::Map::new();
+            for (key, v) in inline.iter() {
+                map.insert(key.to_string(), toml_value_to_json(v));
+            }
+            serde_json::Value::Object(map)
+        }
+    }
+}
+
+/// Maps each JSON pointer (e.g. `/servers/0/port`) reachable from the root
+/// of `document` to the byte span of the TOML source that produced it, so
+/// schema errors reported against the JSON view can be resolved back to a
+/// TOML location.
+fn build_span_table(document: &toml_edit::DocumentMut) -> std::collections::HashMap<String, std::ops::Range<usize>> {
+    let mut table = std::collections::HashMap::new();
+    walk_table(document.as_table(), String::new(), &mut table);
+    table
+}
+
+fn walk_table(
+    table: &toml_edit::Table,
+    prefix: String,
+    table_spans: &mut std::collections::HashMap<String, std::ops::Range<usize>>,
+) {
+    for (key, item) in table.iter() {
+        let pointer = format!("{prefix}/{key}");
+        walk_item(item, pointer, table_spans);
+    }
+}
+
+fn walk_item(
+    item: &toml_edit::Item,
+    pointer: String,
+    table_spans: &mut std::collections::HashMap<String, std::ops::Range<usize>>,
+) {
+    if let Some(span) = item.span() {
+        table_spans.insert(pointer.clone(), span);
+    }
+    match item {
+        toml_edit::Item::Table(t) => walk_table(t, pointer, table_spans),
+        toml_edit::Item::ArrayOfTables(aot) => {
+            for (i, t) in aot.iter().enumerate() {
+                walk_table(t, format!("{pointer}/{i}"), table_spans);
+            }
+        }
+        toml_edit::Item::Value(toml_edit::Value::Array(arr)) => {
+            for (i, v) in arr.iter().enumerate() {
+                let child_pointer = format!("{pointer}/{i}");
+                if let Some(span) = v.span() {
+                    table_spans.insert(child_pointer.clone(), span);
+                }
+                walk_value(v, child_pointer, table_spans);
+            }
+        }
+        toml_edit::Item::Value(toml_edit::Value::InlineTable(inline)) => {
+            for (key, v) in inline.iter() {
+                let child_pointer = format!("{pointer}/{key}");
+                if let Some(span) = v.span() {
+                    table_spans.insert(child_pointer.clone(), span);
+                }
+                walk_value(v, child_pointer, table_spans);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn walk_value(
+    value: &toml_edit::Value,
+    pointer: String,
+    table_spans: &mut std::collections::HashMap<String, std::ops::Range<usize>>,
+) {
+    match value {
+        toml_edit::Value::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                let child_pointer = format!("{pointer}/{i}");
+                if let Some(span) = v.span() {
+                    table_spans.insert(child_pointer.clone(), span);
+                }
+                walk_value(v, child_pointer, table_spans);
+            }
+        }
+        toml_edit::Value::InlineTable(inline) => {
+            for (key, v) in inline.iter() {
+                let child_pointer = format!("{pointer}/{key}");
+                if let Some(span) = v.span() {
+                    table_spans.insert(child_pointer.clone(), span);
+                }
+                walk_value(v, child_pointer, table_spans);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolves a JSON pointer to a byte span, falling back to the nearest
+/// enclosing key's span when the pointer addresses a synthesized or
+/// implicit table that has no span of its own.
+fn resolve_pointer_span(
+    table: &std::collections::HashMap<String, std::ops::Range<usize>>,
+    pointer: &str,
+) -> Option<std::ops::Range<usize>> {
+    let normalized = if pointer.is_empty() { "/" } else { pointer };
+    let mut candidate = normalized.to_string();
+    loop {
+        if let Some(span) = table.get(&candidate) {
+            return Some(span.clone());
+        }
+        match candidate.rfind('/') {
+            Some(0) => {
+                return table.get("").cloned();
+            }
+            Some(idx) => candidate.truncate(idx),
+            None => return None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GetValueResult {
+    ok: bool,
+    value: Option<serde_json::Value>,
+    error: Option<ValidationResult>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EditResult {
+    ok: bool,
+    content: Option<String>,
+    error: Option<ValidationResult>,
+}
+
+/// Reads the value at `dotted_path` (e.g. `servers.alpha.port`) out of a
+/// TOML document as JSON, without modifying it.
+#[wasm_bindgen]
+pub fn get_value(content: &str, dotted_path: &str) -> String {
+    let document = match content.parse::<toml_edit::DocumentMut>() {
+        Ok(d) => d,
         Err(e) => {
-            return serde_json::to_string(&SchemaValidationResult {
-                valid: false,
-                errors: vec![SchemaError {
-                    path: String::from("schema"),
-                    message: format!("Invalid JSON Schema: {:?}", e),
-                }],
+            return serde_json::to_string(&GetValueResult {
+                ok: false,
+                value: None,
+                error: Some(parse_error_result(e.to_string())),
             })
             .unwrap();
         }
     };
 
-    let validation = schema.validate(&json_value);
-    let is_valid = validation.is_valid();
+    let json_value = toml_document_to_json(&document);
 
-    let mut errors_vec = Vec::new();
-    if !is_valid {
-        for error in validation.errors {
-            errors_vec.push(SchemaError {
-                path: error.get_path().to_string(),
-                message: error.get_title().to_string(),
-            });
+    match navigate_json(&json_value, dotted_path) {
+        Some(value) => serde_json::to_string(&GetValueResult {
+            ok: true,
+            value: Some(value.clone()),
+            error: None,
+        })
+        .unwrap(),
+        None => {
+            let error = path_not_found_error(content, &document, dotted_path);
+            serde_json::to_string(&GetValueResult {
+                ok: false,
+                value: None,
+                error: Some(error),
+            })
+            .unwrap()
         }
     }
+}
 
-    let result = SchemaValidationResult {
-        valid: is_valid,
-        errors: errors_vec,
+/// Sets the value at `dotted_path` to `json_value`, creating any missing
+/// intermediate tables along the way, and returns the re-serialized
+/// document. Only the targeted key's formatting changes; everything else
+/// (comments, blank lines, key order) is left untouched by `toml_edit`.
+#[wasm_bindgen]
+pub fn set_value(content: &str, dotted_path: &str, json_value: &str) -> String {
+    let mut document = match content.parse::<toml_edit::DocumentMut>() {
+        Ok(d) => d,
+        Err(e) => return edit_error(parse_error_result(e.to_string())),
     };
 
-    serde_json::to_string(&result).unwrap()
+    let value: serde_json::Value = match serde_json::from_str(json_value) {
+        Ok(v) => v,
+        Err(e) => return edit_error(parse_error_result(format!("Invalid JSON value: {e}"))),
+    };
+
+    let segments: Vec<&str> = dotted_path.split('.').collect();
+    let Some((last, parents)) = segments.split_last() else {
+        return edit_error(parse_error_result(String::from("dotted_path must not be empty")));
+    };
+
+    let item = match json_to_toml_item(&value) {
+        Ok(item) => item,
+        Err(message) => return edit_error(parse_error_result(message)),
+    };
+
+    match navigate_to_parent_creating(document.as_table_mut(), parents) {
+        Ok(parent) => {
+            parent.insert(last, item);
+            edit_ok(document.to_string())
+        }
+        Err(message) => edit_error(path_not_found_error(content, &document, dotted_path).with_message(message)),
+    }
+}
+
+/// Removes the value at `dotted_path` and returns the re-serialized
+/// document, or a structured error if the path doesn't exist.
+#[wasm_bindgen]
+pub fn remove_value(content: &str, dotted_path: &str) -> String {
+    let mut document = match content.parse::<toml_edit::DocumentMut>() {
+        Ok(d) => d,
+        Err(e) => return edit_error(parse_error_result(e.to_string())),
+    };
+
+    let segments: Vec<&str> = dotted_path.split('.').collect();
+    let Some((last, parents)) = segments.split_last() else {
+        return edit_error(parse_error_result(String::from("dotted_path must not be empty")));
+    };
+
+    let removed = match navigate_to_parent_mut(document.as_table_mut(), parents) {
+        Some(parent) => parent.remove(last).is_some(),
+        None => false,
+    };
+
+    if removed {
+        edit_ok(document.to_string())
+    } else {
+        edit_error(path_not_found_error(content, &document, dotted_path))
+    }
+}
+
+fn edit_ok(content: String) -> String {
+    serde_json::to_string(&EditResult {
+        ok: true,
+        content: Some(content),
+        error: None,
+    })
+    .unwrap()
+}
+
+fn edit_error(error: ValidationResult) -> String {
+    serde_json::to_string(&EditResult {
+        ok: false,
+        content: None,
+        error: Some(error),
+    })
+    .unwrap()
+}
+
+fn parse_error_result(message: String) -> ValidationResult {
+    ValidationResult {
+        valid: false,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: Some(message),
+    }
+}
+
+impl ValidationResult {
+    fn with_message(mut self, message: String) -> Self {
+        self.message = Some(message);
+        self
+    }
+}
+
+/// Builds a source-span error for a `dotted_path` that doesn't resolve to
+/// anything in `document`, pointing at the nearest enclosing key.
+fn path_not_found_error(content: &str, document: &toml_edit::DocumentMut, dotted_path: &str) -> ValidationResult {
+    let index = LineIndex::new(content);
+    let span_table = build_span_table(document);
+    let pointer = format!("/{}", dotted_path.replace('.', "/"));
+    let span = resolve_pointer_span(&span_table, &pointer);
+    let (line, column, end_line, end_column) = match span {
+        Some(range) => {
+            let start = index.coords(range.start, content);
+            let end = index.coords(range.end, content);
+            (Some(start.0), Some(start.1), Some(end.0), Some(end.1))
+        }
+        None => (None, None, None, None),
+    };
+    ValidationResult {
+        valid: false,
+        line,
+        column,
+        end_line,
+        end_column,
+        message: Some(format!("path '{dotted_path}' not found")),
+    }
+}
+
+fn navigate_json<'a>(value: &'a serde_json::Value, dotted_path: &str) -> Option<&'a serde_json::Value> {
+    let mut current = value;
+    for segment in dotted_path.split('.') {
+        current = current.as_object()?.get(segment)?;
+    }
+    Some(current)
+}
+
+fn navigate_to_parent_mut<'a>(
+    table: &'a mut toml_edit::Table,
+    segments: &[&str],
+) -> Option<&'a mut toml_edit::Table> {
+    let mut table = table;
+    for segment in segments {
+        table = table.get_mut(segment)?.as_table_mut()?;
+    }
+    Some(table)
+}
+
+/// Like [`navigate_to_parent_mut`], but creates an implicit table for any
+/// missing path segment instead of failing, mirroring how TOML itself
+/// treats intermediate dotted-key tables as implicit until something
+/// populates them directly.
+fn navigate_to_parent_creating<'a>(
+    table: &'a mut toml_edit::Table,
+    segments: &[&str],
+) -> Result<&'a mut toml_edit::Table, String> {
+    let mut table = table;
+    for segment in segments {
+        let entry = table.entry(segment).or_insert_with(|| {
+            let mut t = toml_edit::Table::new();
+            t.set_implicit(true);
+            toml_edit::Item::Table(t)
+        });
+        table = entry
+            .as_table_mut()
+            .ok_or_else(|| format!("'{segment}' is not a table"))?;
+    }
+    Ok(table)
+}
+
+/// Coerces a `serde_json::Value` into the closest `toml_edit` item: TOML
+/// has no `null`, so a JSON `null` is rejected rather than silently
+/// dropped.
+fn json_to_toml_item(value: &serde_json::Value) -> Result<toml_edit::Item, String> {
+    match value {
+        serde_json::Value::Null => Err(String::from("TOML has no representation for null")),
+        serde_json::Value::Object(map) => {
+            let mut table = toml_edit::Table::new();
+            for (key, val) in map {
+                table.insert(key, json_to_toml_item(val)?);
+            }
+            Ok(toml_edit::Item::Table(table))
+        }
+        other => Ok(toml_edit::Item::Value(json_to_toml_value(other)?)),
+    }
+}
+
+fn json_to_toml_value(value: &serde_json::Value) -> Result<toml_edit::Value, String> {
+    match value {
+        serde_json::Value::Null => Err(String::from("TOML has no representation for null")),
+        serde_json::Value::Bool(b) => Ok(toml_edit::Value::from(*b)),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(toml_edit::Value::from(i))
+            } else if let Some(f) = n.as_f64() {
+                Ok(toml_edit::Value::from(f))
+            } else {
+                Err(format!("number out of range: {n}"))
+            }
+        }
+        serde_json::Value::String(s) => Ok(toml_edit::Value::from(s.clone())),
+        serde_json::Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(json_to_toml_value(item)?);
+            }
+            Ok(toml_edit::Value::Array(array))
+        }
+        serde_json::Value::Object(map) => {
+            let mut inline = toml_edit::InlineTable::new();
+            for (key, val) in map {
+                inline.insert(key, json_to_toml_value(val)?);
+            }
+            Ok(toml_edit::Value::InlineTable(inline))
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {}
-fn sanitize_json(value: &mut serde_json::Value) {
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_validator_reuses_compiled_schema_across_calls() {
+        let schema = r#"{"type":"object","required":["port"],"properties":{"port":{"type":"integer"}}}"#;
+        let validator = SchemaValidator::new(schema, None).expect("schema should compile");
+
+        let ok: SchemaValidationResult =
+            serde_json::from_str(&validator.validate("port = 8080")).unwrap();
+        assert!(ok.valid);
+
+        let bad: SchemaValidationResult =
+            serde_json::from_str(&validator.validate("port = \"nope\"")).unwrap();
+        assert!(!bad.valid);
+        assert_eq!(bad.errors.len(), 1);
+
+        // A second validate() call reuses the same compiled schema/scope.
+        let ok_again: SchemaValidationResult =
+            serde_json::from_str(&validator.validate("port = 1")).unwrap();
+        assert!(ok_again.valid);
+    }
+
+    #[test]
+    fn validate_with_schema_matches_validator_for_one_shot_use() {
+        let schema = r#"{"type":"object","properties":{"name":{"type":"string"}}}"#;
+        let result = validate_with_schema("name = 1", schema, None);
+        let parsed: SchemaValidationResult = serde_json::from_str(&result).unwrap();
+        assert!(!parsed.valid);
+
+        let validator = SchemaValidator::new(schema, None).expect("schema should compile");
+        assert_eq!(result, validator.validate("name = 1"));
+    }
+
+    #[test]
+    fn validate_with_schema_reports_invalid_schema_json() {
+        let result = validate_with_schema("a = 1", "not json", None);
+        let parsed: SchemaValidationResult = serde_json::from_str(&result).unwrap();
+        assert!(!parsed.valid);
+        assert_eq!(parsed.errors[0].path, "schema");
+    }
+
+    #[test]
+    fn schema_config_selects_draft_for_anchor_ref_semantics() {
+        let schema = r##"{
+            "type": "object",
+            "properties": { "port": { "$ref": "#limit" } },
+            "$defs": { "limit": { "$anchor": "limit", "type": "integer", "minimum": 1024 } }
+        }"##;
+
+        // draft7 has no notion of the `$anchor` keyword, so `#limit` never
+        // resolves to the `$defs/limit` subschema; the unresolved `$ref` is
+        // reported as an error regardless of the value it's attached to.
+        let draft7 = validate_with_schema(
+            "port = 2000",
+            schema,
+            Some(r#"{"draft":"draft7"}"#.to_string()),
+        );
+        let draft7: SchemaValidationResult = serde_json::from_str(&draft7).unwrap();
+        assert!(!draft7.valid);
+
+        // draft2019-09 resolves `$anchor`-style refs, so the `minimum`
+        // constraint on `limit` applies and 2000 satisfies it.
+        let draft2019_09 = validate_with_schema(
+            "port = 2000",
+            schema,
+            Some(r#"{"draft":"2019-09"}"#.to_string()),
+        );
+        let draft2019_09: SchemaValidationResult = serde_json::from_str(&draft2019_09).unwrap();
+        assert!(draft2019_09.valid);
+    }
+
+    #[test]
+    fn schema_config_resolves_remote_ref() {
+        let mut refs = std::collections::HashMap::new();
+        refs.insert(
+            "https://example.com/port.json".to_string(),
+            serde_json::json!({"type": "integer", "minimum": 1}),
+        );
+        let config = serde_json::json!({ "refs": refs }).to_string();
+        let schema = r#"{"type":"object","properties":{"port":{"$ref":"https://example.com/port.json"}}}"#;
+
+        let ok = validate_with_schema("port = 80", schema, Some(config.clone()));
+        let ok: SchemaValidationResult = serde_json::from_str(&ok).unwrap();
+        assert!(ok.valid);
+
+        let bad = validate_with_schema("port = 0", schema, Some(config));
+        let bad: SchemaValidationResult = serde_json::from_str(&bad).unwrap();
+        assert!(!bad.valid);
+    }
+
+    #[test]
+    fn schema_config_reports_unresolved_remote_ref() {
+        let schema = r#"{"type":"object","properties":{"port":{"$ref":"https://example.com/missing.json"}}}"#;
+        let config = serde_json::json!({
+            "refs": { "https://example.com/other.json": { "type": "integer" } }
+        })
+        .to_string();
+
+        let result = validate_with_schema("port = 1", schema, Some(config));
+        let parsed: SchemaValidationResult = serde_json::from_str(&result).unwrap();
+        assert!(!parsed.valid);
+    }
+
+    #[test]
+    fn date_time_format_rejects_local_datetime() {
+        let schema = r#"{"type":"object","properties":{"at":{"type":"string","format":"date-time"}}}"#;
+
+        let offset = validate_with_schema("at = 2024-01-01T00:00:00Z", schema, None);
+        let offset: SchemaValidationResult = serde_json::from_str(&offset).unwrap();
+        assert!(offset.valid);
+
+        // TOML local date-times have no offset; RFC3339 `date-time` requires one.
+        let local = validate_with_schema("at = 2024-01-01T00:00:00", schema, None);
+        let local: SchemaValidationResult = serde_json::from_str(&local).unwrap();
+        assert!(!local.valid);
+    }
+
+    #[test]
+    fn date_and_time_formats_validate_toml_native_values() {
+        let schema = r#"{"type":"object","properties":{"d":{"type":"string","format":"date"},"t":{"type":"string","format":"time"}}}"#;
+
+        let ok = validate_with_schema("d = 2024-01-01\nt = 12:30:00", schema, None);
+        let ok: SchemaValidationResult = serde_json::from_str(&ok).unwrap();
+        assert!(ok.valid);
+
+        let bad = validate_with_schema("d = \"not-a-date\"\nt = \"nope\"", schema, None);
+        let bad: SchemaValidationResult = serde_json::from_str(&bad).unwrap();
+        assert!(!bad.valid);
+        assert_eq!(bad.errors.len(), 2);
+    }
+
+    #[test]
+    fn duration_format_accepts_iso8601_and_rejects_garbage() {
+        let schema = r#"{"type":"object","properties":{"ttl":{"type":"string","format":"duration"}}}"#;
+
+        let ok = validate_with_schema("ttl = \"P1DT2H\"", schema, None);
+        let ok: SchemaValidationResult = serde_json::from_str(&ok).unwrap();
+        assert!(ok.valid);
+
+        let bad = validate_with_schema("ttl = \"two days\"", schema, None);
+        let bad: SchemaValidationResult = serde_json::from_str(&bad).unwrap();
+        assert!(!bad.valid);
+    }
+
+    #[test]
+    fn convert_to_json_parses_each_format_explicitly() {
+        let toml_result: ConversionResult =
+            serde_json::from_str(&convert_to_json("a = 1", Some("toml".to_string()))).unwrap();
+        assert!(toml_result.ok);
+        assert_eq!(toml_result.json.unwrap(), r#"{"a":1}"#);
+
+        let yaml_result: ConversionResult =
+            serde_json::from_str(&convert_to_json("a: 1", Some("yaml".to_string()))).unwrap();
+        assert!(yaml_result.ok);
+        assert_eq!(yaml_result.json.unwrap(), r#"{"a":1}"#);
+
+        let json_result: ConversionResult =
+            serde_json::from_str(&convert_to_json(r#"{"a":1}"#, Some("json".to_string()))).unwrap();
+        assert!(json_result.ok);
+        assert_eq!(json_result.json.unwrap(), r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn convert_to_json_detects_format_when_none_given() {
+        let result: ConversionResult =
+            serde_json::from_str(&convert_to_json("a = 1", None)).unwrap();
+        assert!(result.ok);
+        assert_eq!(result.json.unwrap(), r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn convert_to_json_reports_unknown_format() {
+        let result: ConversionResult =
+            serde_json::from_str(&convert_to_json("a = 1", Some("xml".to_string()))).unwrap();
+        assert!(!result.ok);
+        assert!(result.error.unwrap().message.unwrap().contains("Unknown format"));
+    }
+
+    #[test]
+    fn validate_toml_terminates_on_unterminated_multiline_string() {
+        let result = validate_toml("a = \"\"\"unterminated\nmore text\nand more");
+        let parsed: TomlValidationResult = serde_json::from_str(&result).unwrap();
+        assert!(!parsed.valid);
+        assert!(!parsed.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn validate_toml_terminates_on_unterminated_array() {
+        let result = validate_toml("arr = [1, 2,\n3, 4");
+        let parsed: TomlValidationResult = serde_json::from_str(&result).unwrap();
+        assert!(!parsed.valid);
+        assert!(!parsed.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn get_value_reads_nested_dotted_path() {
+        let content = "[servers.alpha]\nport = 8080\n";
+        let result: GetValueResult =
+            serde_json::from_str(&get_value(content, "servers.alpha.port")).unwrap();
+        assert!(result.ok);
+        assert_eq!(result.value.unwrap(), serde_json::json!(8080));
+    }
+
+    #[test]
+    fn get_value_reports_missing_path() {
+        let content = "[servers.alpha]\nport = 8080\n";
+        let result: GetValueResult =
+            serde_json::from_str(&get_value(content, "servers.alpha.host")).unwrap();
+        assert!(!result.ok);
+        assert!(result.error.unwrap().message.unwrap().contains("not found"));
+    }
+
+    #[test]
+    fn set_value_preserves_comments_and_formatting() {
+        let content = "# top comment\n[servers.alpha]\nport = 8080\nhost = \"a\" # inline\n";
+        let result: EditResult =
+            serde_json::from_str(&set_value(content, "servers.alpha.port", "9090")).unwrap();
+        assert!(result.ok);
+        let updated = result.content.unwrap();
+        assert!(updated.contains("# top comment"));
+        assert!(updated.contains("port = 9090"));
+        assert!(updated.contains("host = \"a\" # inline"));
+    }
+
+    #[test]
+    fn set_value_creates_missing_intermediate_tables() {
+        let content = "";
+        let result: EditResult =
+            serde_json::from_str(&set_value(content, "servers.alpha.port", "9090")).unwrap();
+        assert!(result.ok);
+        let updated = result.content.unwrap();
+        let reparsed: GetValueResult =
+            serde_json::from_str(&get_value(&updated, "servers.alpha.port")).unwrap();
+        assert!(reparsed.ok);
+        assert_eq!(reparsed.value.unwrap(), serde_json::json!(9090));
+    }
+
+    #[test]
+    fn set_value_rejects_non_table_intermediate_segment() {
+        let content = "port = 8080\n";
+        let result: EditResult =
+            serde_json::from_str(&set_value(content, "port.nested", "1")).unwrap();
+        assert!(!result.ok);
+        assert!(result.error.unwrap().message.unwrap().contains("not a table"));
+    }
+
+    #[test]
+    fn remove_value_drops_key_and_preserves_rest() {
+        let content = "[servers.alpha]\nport = 8080\nhost = \"a\"\n";
+        let result: EditResult =
+            serde_json::from_str(&remove_value(content, "servers.alpha.port")).unwrap();
+        assert!(result.ok);
+        let updated = result.content.unwrap();
+        assert!(!updated.contains("port"));
+        assert!(updated.contains("host = \"a\""));
+    }
+
+    #[test]
+    fn remove_value_reports_missing_path() {
+        let content = "[servers.alpha]\nhost = \"a\"\n";
+        let result: EditResult =
+            serde_json::from_str(&remove_value(content, "servers.alpha.port")).unwrap();
+        assert!(!result.ok);
+        assert!(result.error.unwrap().message.unwrap().contains("not found"));
+    }
+
+    #[test]
+    fn custom_regex_format_is_registered_alongside_builtins() {
+        let schema = r#"{"type":"object","properties":{"id":{"type":"string","format":"slug"}}}"#;
+        let config = serde_json::json!({ "formats": { "slug": "[a-z0-9-]+" } }).to_string();
+
+        let ok = validate_with_schema("id = \"my-slug-1\"", schema, Some(config.clone()));
+        let ok: SchemaValidationResult = serde_json::from_str(&ok).unwrap();
+        assert!(ok.valid);
+
+        let bad = validate_with_schema("id = \"Not A Slug\"", schema, Some(config));
+        let bad: SchemaValidationResult = serde_json::from_str(&bad).unwrap();
+        assert!(!bad.valid);
+    }
+
+    #[test]
+    fn format_keyword_is_checked_through_a_local_ref() {
+        let schema = r##"{
+            "type": "object",
+            "properties": { "id": { "$ref": "#/$defs/Slug" } },
+            "$defs": { "Slug": { "type": "string", "format": "slug" } }
+        }"##;
+        let config = serde_json::json!({ "formats": { "slug": "[a-z0-9-]+" } }).to_string();
+
+        let ok = validate_with_schema("id = \"my-slug-1\"", schema, Some(config.clone()));
+        let ok: SchemaValidationResult = serde_json::from_str(&ok).unwrap();
+        assert!(ok.valid);
+
+        // This must still be checked even though the format lives behind `$ref`.
+        let bad = validate_with_schema("id = \"Not A Slug\"", schema, Some(config));
+        let bad: SchemaValidationResult = serde_json::from_str(&bad).unwrap();
+        assert!(!bad.valid);
+        assert!(bad.errors.iter().any(|e| e.message.contains("slug")));
+    }
+
+    #[test]
+    fn format_keyword_is_checked_through_a_remote_ref() {
+        let mut refs = std::collections::HashMap::new();
+        refs.insert(
+            "https://example.com/slug.json".to_string(),
+            serde_json::json!({"type": "string", "format": "slug"}),
+        );
+        let config = serde_json::json!({
+            "refs": refs,
+            "formats": { "slug": "[a-z0-9-]+" }
+        })
+        .to_string();
+        let schema = r#"{"type":"object","properties":{"id":{"$ref":"https://example.com/slug.json"}}}"#;
+
+        let ok = validate_with_schema("id = \"my-slug-1\"", schema, Some(config.clone()));
+        let ok: SchemaValidationResult = serde_json::from_str(&ok).unwrap();
+        assert!(ok.valid);
+
+        let bad = validate_with_schema("id = \"Not A Slug\"", schema, Some(config));
+        let bad: SchemaValidationResult = serde_json::from_str(&bad).unwrap();
+        assert!(!bad.valid);
+        assert!(bad.errors.iter().any(|e| e.message.contains("slug")));
+    }
+
+    #[test]
+    fn custom_format_cannot_override_a_built_in_name() {
+        let schema = r#"{"type":"object","properties":{"at":{"type":"string","format":"date-time"}}}"#;
+        let config = serde_json::json!({ "formats": { "date-time": ".*" } }).to_string();
+
+        let result = validate_with_schema("at = 2024-01-01T00:00:00Z", schema, Some(config));
+        let parsed: SchemaValidationResult = serde_json::from_str(&result).unwrap();
+        assert!(!parsed.valid);
+        assert!(parsed.errors[0].message.contains("built-in"));
+    }
+}
+
+fn strip_unsupported_keywords(value: &mut serde_json::Value) {
     match value {
         serde_json::Value::Object(obj) => {
-            // Remove keys starting with x-
-            obj.retain(|key, _| !key.starts_with("x-"));
-            // Recursively sanitize
+            obj.retain(|key, _| !UNSUPPORTED_KEYWORDS.contains(&key.as_str()));
             for (_, val) in obj.iter_mut() {
-                sanitize_json(val);
+                strip_unsupported_keywords(val);
             }
         }
         serde_json::Value::Array(arr) => {
             for val in arr.iter_mut() {
-                sanitize_json(val);
+                strip_unsupported_keywords(val);
             }
         }
         _ => {}